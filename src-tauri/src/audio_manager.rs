@@ -1,274 +1,1200 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::mpsc as std_mpsc;
 use std::sync::{Arc, Mutex};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, Host};
 use rubato::{SincFixedIn, Resampler, SincInterpolationParameters, SincInterpolationType, WindowFunction};
+use tauri::Manager;
+use tokio::sync::mpsc;
 
-pub struct AudioManager {
-    stream: Option<cpal::Stream>,
-    is_recording: Arc<Mutex<bool>>,
-    audio_buffer: Arc<Mutex<Vec<f32>>>,
-    actual_sample_rate: u32,  // 实际使用的采样率
+use crate::streaming::StreamingSession;
+
+/// 流式识别窗口的目标时长（毫秒），在 300~600ms 区间内取值以兼顾延迟与识别准确率
+const STREAMING_WINDOW_MS: u32 = 450;
+
+/// VAD 分析帧长（毫秒），对应 16kHz 下的 320 个样本
+const VAD_FRAME_MS: u32 = 20;
+
+/// 可供前端选择的输入设备信息
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DeviceInfo {
+    pub name: String,
+    pub default_sample_rate: u32,
+    pub supported_formats: Vec<String>,
 }
 
-impl AudioManager {
-    pub fn new() -> Result<Self, String> {
-        Ok(Self {
-            stream: None,
-            is_recording: Arc::new(Mutex::new(false)),
-            audio_buffer: Arc::new(Mutex::new(Vec::new())),
-            actual_sample_rate: 48000,  // 默认值，将被实际值覆盖
+/// 基于能量的语音活动检测（VAD）参数
+#[derive(Debug, Clone)]
+pub struct VadConfig {
+    /// 判定为语音所需的能量相对本底噪声的倍数
+    pub threshold_multiplier: f32,
+    /// 连续多少个语音帧才打开门限（避免把瞬时噪声误判为语音）
+    pub open_hangover_frames: usize,
+    /// 连续多少个静音帧才关闭门限（避免裁掉字尾）
+    pub close_hangover_frames: usize,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            threshold_multiplier: 3.0,
+            open_hangover_frames: 3,            // 约 60ms
+            close_hangover_frames: 25,           // 约 500ms
+        }
+    }
+}
+
+/// 静音裁剪/自动停止的诊断信息
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct TrimStats {
+    pub original_samples: usize,
+    pub trimmed_samples: usize,
+}
+
+/// 多声道输入的降混方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelMode {
+    /// 只取第0声道（旧行为，部分设备主麦克风不在0号声道时会录到静音）
+    Mono0,
+    /// 对所有声道求平均后归一化，避免麦克风插在非0声道时采不到声音
+    DownmixAverage,
+    /// 只取指定声道
+    Channel(usize),
+}
+
+impl Default for ChannelMode {
+    fn default() -> Self {
+        ChannelMode::DownmixAverage
+    }
+}
+
+/// 降采样波形预览中的一个桶（min/max 包络），用于前端画滚动波形图
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct WaveformBucket {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// 把振幅转换为 dBFS，静音（0振幅）截断到一个很小的值避免 log(0)
+fn amplitude_to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.max(1e-6).log10()
+}
+
+/// 滚动波形预览最多保留的桶数（约等于最近几秒钟），超出部分从头部丢弃
+const WAVEFORM_BUCKET_CAPACITY: usize = 200;
+
+/// 把一帧已归一化的多声道样本按 `ChannelMode` 降混为单声道
+fn downmix_frame(normalized_frame: &[f32], mode: ChannelMode) -> Option<f32> {
+    match mode {
+        ChannelMode::Mono0 => normalized_frame.first().copied(),
+        ChannelMode::DownmixAverage => {
+            if normalized_frame.is_empty() {
+                return None;
+            }
+            let sum: f32 = normalized_frame.iter().sum();
+            Some((sum / normalized_frame.len() as f32).clamp(-1.0, 1.0))
+        }
+        ChannelMode::Channel(n) => normalized_frame.get(n).copied(),
+    }
+}
+
+/// 计算单帧的 RMS 能量
+fn frame_rms(frame: &[f32]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    let sum_sq: f32 = frame.iter().map(|&s| s * s).sum();
+    (sum_sq / frame.len() as f32).sqrt()
+}
+
+/// 按帧跑一遍基于能量的 VAD，返回每一帧是否处于"门限打开（有语音）"状态。
+/// 本底噪声用最近观测到的最小能量做指数滑动平均，门限用 open/close 两套
+/// hangover 计数器分别控制，避免吞掉短促的气音或裁掉字尾。
+fn vad_gate(data: &[f32], frame_size: usize, config: &VadConfig) -> Vec<bool> {
+    if frame_size == 0 || data.is_empty() {
+        return Vec::new();
+    }
+
+    const NOISE_FLOOR_EMA_ALPHA: f32 = 0.1;
+
+    let mut gate = Vec::with_capacity(data.len() / frame_size + 1);
+    let mut noise_floor = frame_rms(&data[..frame_size.min(data.len())]).max(1e-6);
+    let mut is_open = false;
+    let mut consecutive_speech = 0usize;
+    let mut consecutive_silence = 0usize;
+
+    for frame in data.chunks(frame_size) {
+        let energy = frame_rms(frame);
+
+        // 本底噪声只跟随能量下降的方向做 EMA，避免语音本身把噪声基线顶上去
+        if energy < noise_floor {
+            noise_floor = noise_floor * (1.0 - NOISE_FLOOR_EMA_ALPHA) + energy * NOISE_FLOOR_EMA_ALPHA;
+        }
+        noise_floor = noise_floor.max(1e-6);
+
+        let is_speech_candidate = energy > noise_floor * config.threshold_multiplier;
+
+        if is_speech_candidate {
+            consecutive_speech += 1;
+            consecutive_silence = 0;
+        } else {
+            consecutive_silence += 1;
+            consecutive_speech = 0;
+        }
+
+        if !is_open && consecutive_speech >= config.open_hangover_frames {
+            is_open = true;
+        } else if is_open && consecutive_silence >= config.close_hangover_frames {
+            is_open = false;
+        }
+
+        gate.push(is_open);
+    }
+
+    gate
+}
+
+/// 裁剪掉首尾静音，只保留第一个和最后一个"语音"帧之间的样本
+fn trim_silence(data: &[f32], sample_rate: u32, config: &VadConfig) -> Vec<f32> {
+    let frame_size = ((sample_rate * VAD_FRAME_MS / 1000) as usize).max(1);
+    let gate = vad_gate(data, frame_size, config);
+
+    let first_open = gate.iter().position(|&open| open);
+    let last_open = gate.iter().rposition(|&open| open);
+
+    match (first_open, last_open) {
+        (Some(first), Some(last)) => {
+            let start = first * frame_size;
+            let end = ((last + 1) * frame_size).min(data.len());
+            data[start..end].to_vec()
+        }
+        _ => data.to_vec(),  // 整段都没有检测到语音，原样返回交给上游决定
+    }
+}
+
+/// `vad_gate` 的增量版本：只保留跑 VAD 所需的最少状态（本底噪声、开关门限的
+/// hangover 计数器、不足一帧的尾巴样本），每次只处理新喂入的那一小段样本，
+/// 避免自动停止检测在整段录音上反复重算导致开销随时长变成平方级。
+struct IncrementalVad {
+    frame_size: usize,
+    carry: Vec<f32>,
+    noise_floor: f32,
+    noise_floor_initialized: bool,
+    is_open: bool,
+    consecutive_speech: usize,
+    consecutive_silence: usize,
+    speech_seen: bool,
+    trailing_silent_frames: usize,
+}
+
+impl IncrementalVad {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            frame_size: ((sample_rate * VAD_FRAME_MS / 1000) as usize).max(1),
+            carry: Vec::new(),
+            noise_floor: 1e-6,
+            noise_floor_initialized: false,
+            is_open: false,
+            consecutive_speech: 0,
+            consecutive_silence: 0,
+            speech_seen: false,
+            trailing_silent_frames: 0,
+        }
+    }
+
+    /// 喂入新样本并推进 VAD 状态，返回截至目前的尾部静音时长（毫秒）
+    fn feed(&mut self, samples: &[f32], config: &VadConfig) -> u32 {
+        self.carry.extend_from_slice(samples);
+
+        let mut offset = 0;
+        while self.carry.len() - offset >= self.frame_size {
+            self.process_frame(&self.carry[offset..offset + self.frame_size].to_vec(), config);
+            offset += self.frame_size;
+        }
+        if offset > 0 {
+            self.carry.drain(..offset);
+        }
+
+        (self.trailing_silent_frames as u32) * VAD_FRAME_MS
+    }
+
+    fn process_frame(&mut self, frame: &[f32], config: &VadConfig) {
+        const NOISE_FLOOR_EMA_ALPHA: f32 = 0.1;
+
+        let energy = frame_rms(frame);
+
+        if !self.noise_floor_initialized {
+            self.noise_floor = energy.max(1e-6);
+            self.noise_floor_initialized = true;
+        } else if energy < self.noise_floor {
+            self.noise_floor = self.noise_floor * (1.0 - NOISE_FLOOR_EMA_ALPHA) + energy * NOISE_FLOOR_EMA_ALPHA;
+        }
+        self.noise_floor = self.noise_floor.max(1e-6);
+
+        let is_speech_candidate = energy > self.noise_floor * config.threshold_multiplier;
+        if is_speech_candidate {
+            self.consecutive_speech += 1;
+            self.consecutive_silence = 0;
+        } else {
+            self.consecutive_silence += 1;
+            self.consecutive_speech = 0;
+        }
+
+        let mut just_closed = false;
+        if !self.is_open && self.consecutive_speech >= config.open_hangover_frames {
+            self.is_open = true;
+        } else if self.is_open && self.consecutive_silence >= config.close_hangover_frames {
+            self.is_open = false;
+            just_closed = true;
+        }
+
+        if self.is_open {
+            self.speech_seen = true;
+            self.trailing_silent_frames = 0;
+        } else if just_closed {
+            // 关门前 hangover 期间的静音帧也属于尾部静音，一并计入
+            self.trailing_silent_frames = self.consecutive_silence;
+        } else if self.speech_seen {
+            self.trailing_silent_frames += 1;
+        }
+    }
+}
+
+// 高质量专业重采样（使用rubato库）
+pub(crate) fn resample(data: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate {
+        return data.to_vec();
+    }
+
+    let resample_ratio = to_rate as f64 / from_rate as f64;
+
+    // 创建高质量重采样器
+    let mut resampler = match SincFixedIn::<f32>::new(
+        resample_ratio,
+        2.0,  // 最大重采样比率相对变化
+        SincInterpolationParameters {
+            sinc_len: 256,           // 增加sinc长度提高质量
+            f_cutoff: 0.95,          // 截止频率
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 256,
+            window: WindowFunction::BlackmanHarris2,  // 高质量窗函数
+        },
+        data.len().max(1),
+        1,  // 单声道
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            println!("创建重采样器失败: {:?}, 回退到简单重采样", e);
+            return resample_simple(data, from_rate, to_rate);
+        }
+    };
+
+    // 准备输入数据（二维向量格式，单声道）
+    let input = vec![data.to_vec()];
+
+    // 执行重采样
+    match resampler.process(&input, None) {
+        Ok(output) => {
+            let resampled = output[0].clone();
+            println!("高质量重采样: {}Hz -> {}Hz ({} -> {} 样本)",
+                    from_rate, to_rate, data.len(), resampled.len());
+            resampled
+        },
+        Err(e) => {
+            println!("重采样处理失败: {:?}, 回退到简单重采样", e);
+            resample_simple(data, from_rate, to_rate)
+        }
+    }
+}
+
+// 简单线性插值重采样（备用方案）
+pub(crate) fn resample_simple(data: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let ratio = from_rate as f32 / to_rate as f32;
+    let new_len = (data.len() as f32 / ratio) as usize;
+    let mut resampled = Vec::with_capacity(new_len);
+
+    for i in 0..new_len {
+        let src_pos = i as f32 * ratio;
+        let src_idx = src_pos as usize;
+
+        if src_idx < data.len() - 1 {
+            let frac = src_pos - src_idx as f32;
+            let sample = data[src_idx] * (1.0 - frac) + data[src_idx + 1] * frac;
+            resampled.push(sample);
+        } else if src_idx < data.len() {
+            resampled.push(data[src_idx]);
+        }
+    }
+
+    println!("简单重采样: {}Hz -> {}Hz ({} -> {} 样本)",
+            from_rate, to_rate, data.len(), resampled.len());
+    resampled
+}
+
+pub(crate) fn create_pcm(audio_data: Vec<f32>) -> Result<Vec<u8>, String> {
+    // 直接转换f32到16位PCM，无需WAV头
+    let pcm_data: Vec<u8> = audio_data.iter()
+        .map(|&sample| {
+            let clamped = sample.clamp(-1.0, 1.0);
+            let amplitude = (clamped * i16::MAX as f32) as i16;
+            amplitude.to_le_bytes()
         })
+        .flatten()
+        .collect();
+
+    println!("生成PCM数据: {} 字节 (原始样本: {})", pcm_data.len(), audio_data.len());
+
+    Ok(pcm_data)
+}
+
+/// Opus 单帧时长对应的样本数（60ms @16kHz mono，Opus 支持的最大帧长以换取更高压缩率）
+const OPUS_FRAME_SAMPLES: usize = 960;
+
+/// 把 16kHz 单声道样本编码为 Opus，每帧前面加 4 字节小端长度前缀以便服务端分帧解码
+fn encode_opus(audio_data: &[f32], bitrate: i32) -> Result<Vec<u8>, String> {
+    use audiopus::coder::Encoder;
+    use audiopus::{Application, Bitrate, Channels, SampleRate};
+
+    let mut encoder = Encoder::new(SampleRate::Hz16000, Channels::Mono, Application::Voip)
+        .map_err(|e| format!("创建Opus编码器失败: {}", e))?;
+    encoder.set_bitrate(Bitrate::BitsPerSecond(bitrate))
+        .map_err(|e| format!("设置Opus码率失败: {}", e))?;
+
+    let mut output = Vec::new();
+    let mut encode_buf = [0u8; 4000];
+
+    for chunk in audio_data.chunks(OPUS_FRAME_SAMPLES) {
+        // Opus 要求固定帧长，最后一帧不足时用静音补齐
+        let mut frame = [0f32; OPUS_FRAME_SAMPLES];
+        frame[..chunk.len()].copy_from_slice(chunk);
+
+        let len = encoder.encode_float(&frame, &mut encode_buf)
+            .map_err(|e| format!("Opus编码失败: {}", e))?;
+
+        output.extend_from_slice(&(len as u32).to_le_bytes());
+        output.extend_from_slice(&encode_buf[..len]);
     }
-    
-    pub fn initialize(&mut self) -> Result<(), String> {
+
+    println!("Opus编码: {} 样本 -> {} 字节 ({}bps)", audio_data.len(), output.len(), bitrate);
+    Ok(output)
+}
+
+/// 上传前的音频编码方式。PCM 是默认路径，本地服务场景下无需额外压缩；
+/// Opus 用于降低网络带宽占用，牺牲一点CPU和编码延迟。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioEncoder {
+    Pcm,
+    Opus { bitrate: i32 },
+}
+
+impl AudioEncoder {
+    pub fn encode(&self, audio_data: &[f32]) -> Result<Vec<u8>, String> {
+        match self {
+            AudioEncoder::Pcm => create_pcm(audio_data.to_vec()),
+            AudioEncoder::Opus { bitrate } => encode_opus(audio_data, *bitrate),
+        }
+    }
+
+    /// 对应 FunASR 接口的 `wav_format` 取值
+    pub fn wav_format_tag(&self) -> &'static str {
+        match self {
+            AudioEncoder::Pcm => "pcm",
+            AudioEncoder::Opus { .. } => "opus",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioEncoder::Pcm => "audio/pcm",
+            AudioEncoder::Opus { .. } => "audio/opus",
+        }
+    }
+}
+
+impl Default for AudioEncoder {
+    fn default() -> Self {
+        AudioEncoder::Pcm
+    }
+}
+
+/// 录音上传相关的可配置项
+#[derive(Debug, Clone, Default)]
+pub struct AudioConfig {
+    pub encoder: AudioEncoder,
+}
+
+/// 根据设备名在当前 host 下查找输入设备
+fn find_device_by_name(host: &Host, name: &str) -> Option<Device> {
+    let devices = host.input_devices().ok()?;
+    devices.into_iter().find(|d| d.name().map(|n| n == name).unwrap_or(false))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 发给音频工作线程的指令。工作线程独占持有 `cpal::Stream`，
+/// 所有控制都通过这组指令完成，调用方不再需要直接触碰流或共享缓冲区。
+pub enum AudioCommand {
+    /// 打开已保存的设备（不存在则回退到系统默认设备）
+    Initialize,
+    /// 切换到指定名称的输入设备
+    SelectDevice(String),
+    Start,
+    Stop,
+    /// 暂停：停止采集但保留已录制的数据，供 `Resume` 之后继续追加
+    Pause,
+    Resume,
+    StartStreaming {
+        app_handle: tauri::AppHandle,
+        service_url: String,
+        runtime: tokio::runtime::Handle,
+    },
+    StopStreaming,
+    /// 开始录音并在检测到尾部静音达到 `trailing_silence_ms` 后置位自动停止标记
+    StartAutoStop { trailing_silence_ms: u32 },
+}
+
+/// 工作线程对指令的回应
+pub enum AudioStatus {
+    Started,
+    Stopped(Vec<u8>),
+    Paused,
+    Resumed,
+    DeviceSelected(String),
+    StreamingStarted,
+    StreamingStopped,
+    AutoStopArmed,
+    Error(String),
+}
+
+/// 工作线程内部消息：指令和输入回调送来的采样批次走同一个 channel，
+/// 这样工作线程只需要在一个循环里顺序处理，不需要额外的跨线程锁。
+enum WorkerMessage {
+    Command(AudioCommand),
+    Samples(Vec<f32>),
+}
+
+/// 音频工作线程独占持有的状态。只会被这一个线程访问和修改。
+struct AudioWorker {
+    stream: Option<cpal::Stream>,
+    actual_sample_rate: u32,
+    current_device_name: Option<String>,
+    audio_buffer: Vec<f32>,
+
+    window_buffer: Vec<f32>,
+    window_threshold_samples: usize,
+    streaming_tx: Option<mpsc::UnboundedSender<(Vec<f32>, bool)>>,
+    streaming_session: Option<StreamingSession>,
+    /// 流式上传任务实际使用的源采样率，设备热切换改变采样率时就地更新，
+    /// 这样已经在跑的 `StreamingSession` 不需要重启也能用正确的速率重采样
+    streaming_sample_rate: Option<Arc<AtomicU32>>,
+
+    vad_config: VadConfig,
+    /// 自动停止模式下的增量 VAD 状态，按 `StartAutoStop` 各开一份，停止后清空
+    auto_stop_vad: Option<IncrementalVad>,
+
+    /// 滚动波形预览的增量桶：每次广播只把上次广播之后新增的那段样本算成一个桶
+    /// 追加进来，不重算历史，桶数超过 `WAVEFORM_BUCKET_CAPACITY` 时从头部丢弃
+    waveform_buckets: VecDeque<WaveformBucket>,
+    /// `audio_buffer` 中已经计入 `waveform_buckets` 的样本数
+    waveform_tail_offset: usize,
+
+    // 与 AudioManager / cpal 回调共享的轻量状态（原子量/小锁，不涉及 Stream 本身）
+    is_recording: Arc<AtomicBool>,
+    is_paused: Arc<AtomicBool>,
+    is_streaming: Arc<AtomicBool>,
+    auto_stop: Arc<Mutex<Option<u32>>>,
+    auto_stop_triggered: Arc<AtomicBool>,
+    channel_mode: Arc<Mutex<ChannelMode>>,
+    audio_config: Arc<Mutex<AudioConfig>>,
+    last_trim_stats: Arc<Mutex<Option<TrimStats>>>,
+    app_handle: Arc<Mutex<Option<tauri::AppHandle>>>,
+    /// 当前已打开设备的声道数，供切换降混声道时校验索引范围
+    channel_count: Arc<AtomicUsize>,
+
+    sample_tx: std_mpsc::Sender<WorkerMessage>,
+    status_tx: std_mpsc::Sender<AudioStatus>,
+
+    /// 上一次广播电平/波形事件的时间，用于把频率节流到约 30Hz，
+    /// 避免在每个 cpal 回调（通常远高于30Hz）都重新计算整段波形包络
+    last_metering_emit: std::time::Instant,
+}
+
+/// 电平/波形事件的广播间隔，约 30Hz
+const METERING_INTERVAL: std::time::Duration = std::time::Duration::from_millis(33);
+
+impl AudioWorker {
+    fn handle_message(&mut self, message: WorkerMessage) {
+        match message {
+            WorkerMessage::Samples(batch) => self.handle_samples(batch),
+            WorkerMessage::Command(command) => {
+                let status = self.handle_command(command);
+                let _ = self.status_tx.send(status);
+            }
+        }
+    }
+
+    fn handle_samples(&mut self, batch: Vec<f32>) {
+        if batch.is_empty() || !self.is_recording.load(Ordering::SeqCst) || self.is_paused.load(Ordering::SeqCst) {
+            return;
+        }
+
+        self.audio_buffer.extend_from_slice(&batch);
+
+        if self.is_streaming.load(Ordering::SeqCst) {
+            for &sample in &batch {
+                self.window_buffer.push(sample);
+                if self.window_buffer.len() >= self.window_threshold_samples {
+                    let window: Vec<f32> = self.window_buffer.drain(..).collect();
+                    if let Some(tx) = &self.streaming_tx {
+                        let _ = tx.send((window, false));
+                    }
+                }
+            }
+        }
+
+        // 电平/波形事件节流到约30Hz：cpal 回调频率通常远高于这个值，
+        // 每次回调都重新计算整段波形包络会让开销随录音时长变为平方级，
+        // 并挤占这个线程本该用来实时消费音频样本的时间
+        if self.last_metering_emit.elapsed() >= METERING_INTERVAL {
+            self.last_metering_emit = std::time::Instant::now();
+
+            let peak = batch.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+            let rms = frame_rms(&batch);
+
+            // 波形包络只对"上次广播之后新增"的那段样本算一个桶再追加进滚动队列，
+            // 不重新扫描整段录音，避免开销随录音时长变成平方级
+            let waveform_start = self.waveform_tail_offset.min(self.audio_buffer.len());
+            let new_slice = &self.audio_buffer[waveform_start..];
+            if !new_slice.is_empty() {
+                let min = new_slice.iter().cloned().fold(f32::INFINITY, f32::min);
+                let max = new_slice.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+                self.waveform_buckets.push_back(WaveformBucket { min, max });
+                while self.waveform_buckets.len() > WAVEFORM_BUCKET_CAPACITY {
+                    self.waveform_buckets.pop_front();
+                }
+                self.waveform_tail_offset = self.audio_buffer.len();
+            }
+
+            if let Some(handle) = self.app_handle.lock().unwrap().clone() {
+                let _ = handle.emit_all("audio_level", serde_json::json!({
+                    "peak": amplitude_to_db(peak),
+                    "rms": amplitude_to_db(rms),
+                    "timestamp": now_ms(),
+                }));
+                let waveform: Vec<WaveformBucket> = self.waveform_buckets.iter().copied().collect();
+                let _ = handle.emit_all("audio_waveform", waveform);
+            }
+        }
+
+        if let Some(trailing_ms) = *self.auto_stop.lock().unwrap() {
+            if let Some(vad_state) = &mut self.auto_stop_vad {
+                let trailing_silence_ms = vad_state.feed(&batch, &self.vad_config);
+                if trailing_silence_ms >= trailing_ms {
+                    self.auto_stop_triggered.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+
+    fn handle_command(&mut self, command: AudioCommand) -> AudioStatus {
+        match command {
+            AudioCommand::Initialize => {
+                let device_name = self.current_device_name.clone();
+                match self.open_device(device_name) {
+                    Ok(()) => AudioStatus::DeviceSelected(
+                        self.current_device_name.clone().unwrap_or_else(|| "default".to_string())
+                    ),
+                    Err(e) => AudioStatus::Error(e),
+                }
+            }
+            AudioCommand::SelectDevice(name) => {
+                // 显式选择的设备不存在时直接报错，不悄悄回退到默认设备
+                if find_device_by_name(&cpal::default_host(), &name).is_none() {
+                    return AudioStatus::Error(format!("未找到音频输入设备: {}", name));
+                }
+
+                let has_recording = self.is_recording.load(Ordering::SeqCst);
+                let was_recording = has_recording && !self.is_paused.load(Ordering::SeqCst);
+                let previous_rate = self.actual_sample_rate;
+
+                match self.open_device(Some(name.clone())) {
+                    Ok(()) => {
+                        // 切到新设备后采样率可能变了；已经录好的那一段（包括还没攒够
+                        // 一个流式窗口的 window_buffer）是按旧采样率采集的，必须先
+                        // 重采样到新速率再继续追加，否则后面要么在 do_stop 的整体
+                        // 重采样里被当成同一速率处理，要么在还在跑的流式上传任务里
+                        // 被用错误的源速率重采样，两种情况都会让拼接处的音频损坏
+                        if has_recording && previous_rate != self.actual_sample_rate {
+                            if !self.audio_buffer.is_empty() {
+                                self.audio_buffer = resample(&self.audio_buffer, previous_rate, self.actual_sample_rate);
+                            }
+                            if !self.window_buffer.is_empty() {
+                                self.window_buffer = resample(&self.window_buffer, previous_rate, self.actual_sample_rate);
+                            }
+                            if let Some(rate_handle) = &self.streaming_sample_rate {
+                                rate_handle.store(self.actual_sample_rate, Ordering::SeqCst);
+                            }
+                        }
+
+                        if was_recording {
+                            if let Some(stream) = &self.stream {
+                                if let Err(e) = stream.play() {
+                                    return AudioStatus::Error(format!("启动音频流失败: {}", e));
+                                }
+                            }
+                        }
+                        AudioStatus::DeviceSelected(name)
+                    }
+                    Err(e) => AudioStatus::Error(e),
+                }
+            }
+            AudioCommand::Start => self.do_start(),
+            AudioCommand::Stop => self.do_stop(),
+            AudioCommand::Pause => self.do_pause(),
+            AudioCommand::Resume => self.do_resume(),
+            AudioCommand::StartStreaming { app_handle, service_url, runtime } => {
+                self.do_start_streaming(app_handle, service_url, runtime)
+            }
+            AudioCommand::StopStreaming => self.do_stop_streaming(),
+            AudioCommand::StartAutoStop { trailing_silence_ms } => {
+                self.auto_stop_triggered.store(false, Ordering::SeqCst);
+                *self.auto_stop.lock().unwrap() = Some(trailing_silence_ms);
+                match self.do_start() {
+                    AudioStatus::Started => {
+                        self.auto_stop_vad = Some(IncrementalVad::new(self.actual_sample_rate));
+                        AudioStatus::AutoStopArmed
+                    }
+                    other => other,
+                }
+            }
+        }
+    }
+
+    /// 打开输入设备并重建音频流。`device_name` 为 `None` 时使用系统默认设备；
+    /// 指定了名字但设备已不存在时，回退到默认设备并清空保存的设备名。
+    fn open_device(&mut self, device_name: Option<String>) -> Result<(), String> {
         let host = cpal::default_host();
-        let device = host.default_input_device()
-            .ok_or("未找到音频输入设备")?;
-        
-        // 获取设备默认配置（使用设备支持的配置）
+
+        let (device, opened_name) = match device_name {
+            Some(name) => match find_device_by_name(&host, &name) {
+                Some(device) => (device, Some(name)),
+                None => {
+                    println!("保存的音频输入设备 \"{}\" 已不存在，回退到默认设备", name);
+                    let device = host.default_input_device().ok_or("未找到音频输入设备")?;
+                    (device, None)
+                }
+            },
+            None => {
+                let device = host.default_input_device().ok_or("未找到音频输入设备")?;
+                (device, None)
+            }
+        };
+
         let default_config = device.default_input_config()
             .map_err(|e| format!("获取音频配置失败: {}", e))?;
-        
-        // 记录实际的采样率
+
         self.actual_sample_rate = default_config.sample_rate().0;
         println!("设备默认采样率: {}Hz", self.actual_sample_rate);
-        
-        // 使用设备的默认配置
+        self.window_threshold_samples =
+            (self.actual_sample_rate as u64 * STREAMING_WINDOW_MS as u64 / 1000) as usize;
+
         let config: cpal::StreamConfig = default_config.config();
-        
-        let is_recording = self.is_recording.clone();
-        let buffer = self.audio_buffer.clone();
-        let channels = config.channels as usize;
-        
-        // 根据实际的采样格式创建流
+        let channels = (config.channels as usize).max(1);
+        self.channel_count.store(channels, Ordering::SeqCst);
+        let channel_mode = self.channel_mode.clone();
+        let sample_tx = self.sample_tx.clone();
+
+        // 根据实际的采样格式创建流。回调只负责按声道模式降混成单声道样本，
+        // 再整批通过 channel 交给工作线程，不再直接触碰共享缓冲区。
         let stream = match default_config.sample_format() {
             cpal::SampleFormat::I16 => {
-                let buffer_clone = buffer.clone();
-                let is_recording_clone = is_recording.clone();
+                let channel_mode = channel_mode.clone();
+                let sample_tx = sample_tx.clone();
                 device.build_input_stream(
                     &config,
                     move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        if *is_recording_clone.lock().unwrap() {
-                            let mut audio_buffer = buffer_clone.lock().unwrap();
-                            // 如果是多声道，只取第一个声道
-                            for chunk in data.chunks(channels) {
-                                if let Some(&sample) = chunk.first() {
-                                    let normalized = sample as f32 / i16::MAX as f32;
-                                    audio_buffer.push(normalized);
-                                }
+                        let mode = *channel_mode.lock().unwrap();
+                        let mut batch = Vec::with_capacity(data.len() / channels + 1);
+                        for chunk in data.chunks(channels) {
+                            let normalized_frame: Vec<f32> = chunk.iter()
+                                .map(|&s| s as f32 / i16::MAX as f32)
+                                .collect();
+                            if let Some(sample) = downmix_frame(&normalized_frame, mode) {
+                                batch.push(sample);
                             }
                         }
+                        let _ = sample_tx.send(WorkerMessage::Samples(batch));
                     },
                     |err| eprintln!("音频流错误: {}", err),
-                    None
+                    None,
                 )
-            },
+            }
             cpal::SampleFormat::U16 => {
-                let buffer_clone = buffer.clone();
-                let is_recording_clone = is_recording.clone();
+                let channel_mode = channel_mode.clone();
+                let sample_tx = sample_tx.clone();
                 device.build_input_stream(
                     &config,
                     move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                        if *is_recording_clone.lock().unwrap() {
-                            let mut audio_buffer = buffer_clone.lock().unwrap();
-                            for chunk in data.chunks(channels) {
-                                if let Some(&sample) = chunk.first() {
-                                    let normalized = (sample as f32 - 32768.0) / 32768.0;
-                                    audio_buffer.push(normalized);
-                                }
+                        let mode = *channel_mode.lock().unwrap();
+                        let mut batch = Vec::with_capacity(data.len() / channels + 1);
+                        for chunk in data.chunks(channels) {
+                            let normalized_frame: Vec<f32> = chunk.iter()
+                                .map(|&s| (s as f32 - 32768.0) / 32768.0)
+                                .collect();
+                            if let Some(sample) = downmix_frame(&normalized_frame, mode) {
+                                batch.push(sample);
                             }
                         }
+                        let _ = sample_tx.send(WorkerMessage::Samples(batch));
                     },
                     |err| eprintln!("音频流错误: {}", err),
-                    None
+                    None,
                 )
-            },
+            }
             cpal::SampleFormat::F32 => {
-                let buffer_clone = buffer.clone();
-                let is_recording_clone = is_recording.clone();
+                let channel_mode = channel_mode.clone();
+                let sample_tx = sample_tx.clone();
                 device.build_input_stream(
                     &config,
                     move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        if *is_recording_clone.lock().unwrap() {
-                            let mut audio_buffer = buffer_clone.lock().unwrap();
-                            // 如果是多声道，只取第一个声道
-                            for chunk in data.chunks(channels) {
-                                if let Some(&sample) = chunk.first() {
-                                    audio_buffer.push(sample);
-                                }
+                        let mode = *channel_mode.lock().unwrap();
+                        let mut batch = Vec::with_capacity(data.len() / channels + 1);
+                        for chunk in data.chunks(channels) {
+                            if let Some(sample) = downmix_frame(chunk, mode) {
+                                batch.push(sample);
                             }
                         }
+                        let _ = sample_tx.send(WorkerMessage::Samples(batch));
                     },
                     |err| eprintln!("音频流错误: {}", err),
-                    None
+                    None,
                 )
-            },
+            }
             sample_format => return Err(format!("不支持的音频格式: {:?}", sample_format)),
         }.map_err(|e| format!("创建音频流失败: {}", e))?;
-        
+
         // 立即暂停流（不显示录音指示器）
         stream.pause().map_err(|e| format!("暂停流失败: {}", e))?;
-        
+
         self.stream = Some(stream);
+        self.current_device_name = opened_name;
         println!("音频管理器初始化完成");
         println!("  采样率: {}Hz", self.actual_sample_rate);
         println!("  声道数: {}", config.channels);
         println!("  采样格式: {:?}", default_config.sample_format());
-        
+
         Ok(())
     }
-    
-    pub fn start_recording(&mut self) -> Result<(), String> {
-        if *self.is_recording.lock().unwrap() {
-            return Err("Already recording".to_string());
-        }
-        
-        // 清空缓冲区准备新的录音
-        self.audio_buffer.lock().unwrap().clear();
-        
-        // 设置录音状态
-        *self.is_recording.lock().unwrap() = true;
-        
-        // 启动音频流（现在才显示录音指示器）
+
+    fn do_start(&mut self) -> AudioStatus {
+        if self.is_recording.load(Ordering::SeqCst) {
+            return AudioStatus::Error("Already recording".to_string());
+        }
+
+        self.audio_buffer.clear();
+        self.window_buffer.clear();
+        self.waveform_buckets.clear();
+        self.waveform_tail_offset = 0;
+        self.is_paused.store(false, Ordering::SeqCst);
+        self.is_recording.store(true, Ordering::SeqCst);
+
         if let Some(stream) = &self.stream {
-            stream.play().map_err(|e| format!("启动音频流失败: {}", e))?;
+            if let Err(e) = stream.play() {
+                self.is_recording.store(false, Ordering::SeqCst);
+                return AudioStatus::Error(format!("启动音频流失败: {}", e));
+            }
         }
-        
+
         println!("开始录音");
-        Ok(())
+        AudioStatus::Started
     }
-    
-    pub fn stop_recording(&mut self) -> Result<Vec<u8>, String> {
-        if !*self.is_recording.lock().unwrap() {
-            return Err("Not recording".to_string());
-        }
-        
-        // 停止录音状态
-        *self.is_recording.lock().unwrap() = false;
-        
-        // 暂停音频流（隐藏录音指示器）
+
+    fn do_stop(&mut self) -> AudioStatus {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return AudioStatus::Error("Not recording".to_string());
+        }
+
+        self.is_recording.store(false, Ordering::SeqCst);
+        self.is_paused.store(false, Ordering::SeqCst);
+        self.is_streaming.store(false, Ordering::SeqCst);
+        *self.auto_stop.lock().unwrap() = None;
+        self.auto_stop_vad = None;
+        self.streaming_tx = None;
+        self.streaming_session = None;
+        self.streaming_sample_rate = None;
+
         if let Some(stream) = &self.stream {
-            stream.pause().map_err(|e| format!("暂停音频流失败: {}", e))?;
+            if let Err(e) = stream.pause() {
+                return AudioStatus::Error(format!("暂停音频流失败: {}", e));
+            }
         }
-        
+
         // 等待一点时间确保所有音频数据都被处理
         std::thread::sleep(std::time::Duration::from_millis(100));
-        
-        // 获取录音数据
-        let audio_data = {
-            let buffer = self.audio_buffer.lock().unwrap();
-            buffer.clone()
-        };
-        
-        println!("停止录音 - 采集了 {} 个样本 ({:.2}秒)", 
-                audio_data.len(), 
+
+        let audio_data = std::mem::take(&mut self.audio_buffer);
+        println!("停止录音 - 采集了 {} 个样本 ({:.2}秒)",
+                audio_data.len(),
                 audio_data.len() as f32 / self.actual_sample_rate as f32);
-        
+
         if audio_data.is_empty() {
-            return Err("没有录制到音频数据".to_string());
+            return AudioStatus::Error("没有录制到音频数据".to_string());
         }
-        
+
+        // 裁剪掉首尾静音，减少上传体积和延迟
+        let original_samples = audio_data.len();
+        let trimmed_data = trim_silence(&audio_data, self.actual_sample_rate, &self.vad_config);
+        let trim_stats = TrimStats { original_samples, trimmed_samples: trimmed_data.len() };
+        println!("静音裁剪: {} -> {} 个样本", trim_stats.original_samples, trim_stats.trimmed_samples);
+        *self.last_trim_stats.lock().unwrap() = Some(trim_stats);
+
         // 如果采样率不是16kHz，需要重采样
         let resampled_data = if self.actual_sample_rate != 16000 {
-            self.resample(&audio_data, self.actual_sample_rate, 16000)
+            resample(&trimmed_data, self.actual_sample_rate, 16000)
         } else {
-            audio_data
+            trimmed_data
         };
-        
-        // 直接转换为PCM格式（无WAV头）
-        self.create_pcm(resampled_data)
-    }
-    
-    // 高质量专业重采样（使用rubato库）
-    fn resample(&self, data: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-        if from_rate == to_rate {
-            return data.to_vec();
-        }
-        
-        let resample_ratio = to_rate as f64 / from_rate as f64;
-        
-        // 创建高质量重采样器
-        let mut resampler = match SincFixedIn::<f32>::new(
-            resample_ratio,
-            2.0,  // 最大重采样比率相对变化
-            SincInterpolationParameters {
-                sinc_len: 256,           // 增加sinc长度提高质量
-                f_cutoff: 0.95,          // 截止频率
-                interpolation: SincInterpolationType::Linear,
-                oversampling_factor: 256,
-                window: WindowFunction::BlackmanHarris2,  // 高质量窗函数
-            },
-            data.len(),
-            1,  // 单声道
-        ) {
-            Ok(r) => r,
-            Err(e) => {
-                println!("创建重采样器失败: {:?}, 回退到简单重采样", e);
-                return self.resample_simple(data, from_rate, to_rate);
+
+        // 按配置的编码器转换（默认PCM，可选Opus压缩）
+        let encoder = self.audio_config.lock().unwrap().encoder;
+        match encoder.encode(&resampled_data) {
+            Ok(bytes) => AudioStatus::Stopped(bytes),
+            Err(e) => AudioStatus::Error(e),
+        }
+    }
+
+    /// 暂停：保留已录制的数据，只是停止继续采集，配合 `do_resume` 实现断点续录
+    fn do_pause(&mut self) -> AudioStatus {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return AudioStatus::Error("Not recording".to_string());
+        }
+
+        self.is_paused.store(true, Ordering::SeqCst);
+        if let Some(stream) = &self.stream {
+            if let Err(e) = stream.pause() {
+                return AudioStatus::Error(format!("暂停音频流失败: {}", e));
             }
-        };
-        
-        // 准备输入数据（二维向量格式，单声道）
-        let input = vec![data.to_vec()];
-        
-        // 执行重采样
-        match resampler.process(&input, None) {
-            Ok(output) => {
-                let resampled = output[0].clone();
-                println!("高质量重采样: {}Hz -> {}Hz ({} -> {} 样本)", 
-                        from_rate, to_rate, data.len(), resampled.len());
-                resampled
-            },
-            Err(e) => {
-                println!("重采样处理失败: {:?}, 回退到简单重采样", e);
-                self.resample_simple(data, from_rate, to_rate)
+        }
+
+        println!("暂停录音，已采集 {} 个样本", self.audio_buffer.len());
+        AudioStatus::Paused
+    }
+
+    fn do_resume(&mut self) -> AudioStatus {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return AudioStatus::Error("Not recording".to_string());
+        }
+
+        self.is_paused.store(false, Ordering::SeqCst);
+        if let Some(stream) = &self.stream {
+            if let Err(e) = stream.play() {
+                return AudioStatus::Error(format!("恢复录音失败: {}", e));
             }
         }
+
+        println!("恢复录音");
+        AudioStatus::Resumed
     }
-    
-    // 简单线性插值重采样（备用方案）
-    fn resample_simple(&self, data: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-        let ratio = from_rate as f32 / to_rate as f32;
-        let new_len = (data.len() as f32 / ratio) as usize;
-        let mut resampled = Vec::with_capacity(new_len);
-        
-        for i in 0..new_len {
-            let src_pos = i as f32 * ratio;
-            let src_idx = src_pos as usize;
-            
-            if src_idx < data.len() - 1 {
-                let frac = src_pos - src_idx as f32;
-                let sample = data[src_idx] * (1.0 - frac) + data[src_idx + 1] * frac;
-                resampled.push(sample);
-            } else if src_idx < data.len() {
-                resampled.push(data[src_idx]);
+
+    fn do_start_streaming(&mut self, app_handle: tauri::AppHandle, service_url: String, runtime: tokio::runtime::Handle) -> AudioStatus {
+        if self.is_recording.load(Ordering::SeqCst) {
+            return AudioStatus::Error("Already recording".to_string());
+        }
+
+        self.audio_buffer.clear();
+        self.window_buffer.clear();
+        self.waveform_buckets.clear();
+        self.waveform_tail_offset = 0;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.streaming_tx = Some(tx);
+
+        let sample_rate_handle = Arc::new(AtomicU32::new(self.actual_sample_rate));
+        self.streaming_sample_rate = Some(sample_rate_handle.clone());
+        self.streaming_session = Some(StreamingSession::start(app_handle, service_url, sample_rate_handle, rx, runtime));
+
+        self.is_paused.store(false, Ordering::SeqCst);
+        self.is_streaming.store(true, Ordering::SeqCst);
+        self.is_recording.store(true, Ordering::SeqCst);
+
+        if let Some(stream) = &self.stream {
+            if let Err(e) = stream.play() {
+                return AudioStatus::Error(format!("启动音频流失败: {}", e));
             }
         }
-        
-        println!("简单重采样: {}Hz -> {}Hz ({} -> {} 样本)", 
-                from_rate, to_rate, data.len(), resampled.len());
-        resampled
-    }
-    
-    fn create_pcm(&self, audio_data: Vec<f32>) -> Result<Vec<u8>, String> {
-        // 直接转换f32到16位PCM，无需WAV头
-        let pcm_data: Vec<u8> = audio_data.iter()
-            .map(|&sample| {
-                let clamped = sample.clamp(-1.0, 1.0);
-                let amplitude = (clamped * i16::MAX as f32) as i16;
-                amplitude.to_le_bytes()
-            })
-            .flatten()
-            .collect();
-        
-        println!("生成PCM数据: {} 字节 (原始样本: {})", pcm_data.len(), audio_data.len());
-        
-        Ok(pcm_data)
-    }
-    
-    pub fn is_recording(&self) -> bool {
-        *self.is_recording.lock().unwrap()
+
+        println!("开始流式录音");
+        AudioStatus::StreamingStarted
     }
+
+    fn do_stop_streaming(&mut self) -> AudioStatus {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return AudioStatus::Error("Not recording".to_string());
+        }
+
+        self.is_recording.store(false, Ordering::SeqCst);
+        self.is_streaming.store(false, Ordering::SeqCst);
+
+        if let Some(stream) = &self.stream {
+            if let Err(e) = stream.pause() {
+                return AudioStatus::Error(format!("暂停音频流失败: {}", e));
+            }
+        }
+
+        let remaining: Vec<f32> = self.window_buffer.drain(..).collect();
+        if let Some(tx) = self.streaming_tx.take() {
+            let _ = tx.send((remaining, true));
+        }
+        self.streaming_session = None;
+        self.streaming_sample_rate = None;
+
+        println!("停止流式录音");
+        AudioStatus::StreamingStopped
+    }
+}
+
+/// 面向 Tauri 命令的句柄：只持有指令发送端和状态接收端，
+/// 不再直接共享 `cpal::Stream` 或原始采样缓冲区，因此天然是 `Send`
+/// （状态接收端不是 `Sync`，跨线程共享时放在 `Mutex` 里），不需要任何 `unsafe impl`。
+pub struct AudioManager {
+    command_tx: std_mpsc::Sender<WorkerMessage>,
+    status_rx: std_mpsc::Receiver<AudioStatus>,
+
+    is_recording: Arc<AtomicBool>,
+    auto_stop_triggered: Arc<AtomicBool>,
+    auto_stop: Arc<Mutex<Option<u32>>>,
+    channel_mode: Arc<Mutex<ChannelMode>>,
+    audio_config: Arc<Mutex<AudioConfig>>,
+    last_trim_stats: Arc<Mutex<Option<TrimStats>>>,
+    app_handle: Arc<Mutex<Option<tauri::AppHandle>>>,
+    channel_count: Arc<AtomicUsize>,
 }
 
-unsafe impl Send for AudioManager {}
-unsafe impl Sync for AudioManager {}
\ No newline at end of file
+impl AudioManager {
+    pub fn new() -> Result<Self, String> {
+        let (command_tx, worker_rx) = std_mpsc::channel::<WorkerMessage>();
+        let (status_tx, status_rx) = std_mpsc::channel::<AudioStatus>();
+
+        let is_recording = Arc::new(AtomicBool::new(false));
+        let is_paused = Arc::new(AtomicBool::new(false));
+        let is_streaming = Arc::new(AtomicBool::new(false));
+        let auto_stop = Arc::new(Mutex::new(None));
+        let auto_stop_triggered = Arc::new(AtomicBool::new(false));
+        let channel_mode = Arc::new(Mutex::new(ChannelMode::default()));
+        let audio_config = Arc::new(Mutex::new(AudioConfig::default()));
+        let last_trim_stats = Arc::new(Mutex::new(None));
+        let app_handle = Arc::new(Mutex::new(None));
+        let channel_count = Arc::new(AtomicUsize::new(1));
+
+        let manager = Self {
+            command_tx: command_tx.clone(),
+            status_rx,
+            is_recording: is_recording.clone(),
+            auto_stop_triggered: auto_stop_triggered.clone(),
+            auto_stop: auto_stop.clone(),
+            channel_mode: channel_mode.clone(),
+            audio_config: audio_config.clone(),
+            last_trim_stats: last_trim_stats.clone(),
+            app_handle: app_handle.clone(),
+            channel_count: channel_count.clone(),
+        };
+
+        std::thread::spawn(move || {
+            let mut worker = AudioWorker {
+                stream: None,
+                actual_sample_rate: 48000,
+                current_device_name: None,
+                audio_buffer: Vec::new(),
+                window_buffer: Vec::new(),
+                window_threshold_samples: 0,
+                streaming_tx: None,
+                streaming_session: None,
+                streaming_sample_rate: None,
+                vad_config: VadConfig::default(),
+                auto_stop_vad: None,
+                waveform_buckets: VecDeque::new(),
+                waveform_tail_offset: 0,
+                is_recording,
+                is_paused,
+                is_streaming,
+                auto_stop,
+                auto_stop_triggered,
+                channel_mode,
+                audio_config,
+                last_trim_stats,
+                app_handle,
+                channel_count,
+                sample_tx: command_tx,
+                status_tx,
+                last_metering_emit: std::time::Instant::now(),
+            };
+
+            for message in worker_rx {
+                worker.handle_message(message);
+            }
+        });
+
+        Ok(manager)
+    }
+
+    /// 阻塞等待工作线程对上一条指令的回应
+    fn await_reply(&self) -> AudioStatus {
+        match self.status_rx.recv() {
+            Ok(status) => status,
+            Err(_) => AudioStatus::Error("音频工作线程已退出".to_string()),
+        }
+    }
+
+    fn send_command(&self, command: AudioCommand) -> AudioStatus {
+        if self.command_tx.send(WorkerMessage::Command(command)).is_err() {
+            return AudioStatus::Error("音频工作线程已退出".to_string());
+        }
+        self.await_reply()
+    }
+
+    pub fn initialize(&mut self) -> Result<(), String> {
+        match self.send_command(AudioCommand::Initialize) {
+            AudioStatus::DeviceSelected(_) => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// 枚举系统当前可用的音频输入设备。这是纯读操作，不涉及工作线程持有的流状态。
+    pub fn list_input_devices(&self) -> Result<Vec<DeviceInfo>, String> {
+        let host = cpal::default_host();
+        let devices = host.input_devices()
+            .map_err(|e| format!("枚举音频输入设备失败: {}", e))?;
+
+        let mut infos = Vec::new();
+        for device in devices {
+            let name = match device.name() {
+                Ok(name) => name,
+                Err(_) => continue,  // 无法获取名称的设备直接跳过
+            };
+
+            let default_sample_rate = device.default_input_config()
+                .map(|config| config.sample_rate().0)
+                .unwrap_or(0);
+
+            let supported_formats: Vec<String> = device.supported_input_configs()
+                .map(|configs| configs.map(|c| format!("{:?}", c.sample_format())).collect())
+                .unwrap_or_default();
+
+            infos.push(DeviceInfo { name, default_sample_rate, supported_formats });
+        }
+
+        Ok(infos)
+    }
+
+    pub fn select_input_device(&mut self, name: &str) -> Result<(), String> {
+        match self.send_command(AudioCommand::SelectDevice(name.to_string())) {
+            AudioStatus::DeviceSelected(_) => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn start_recording(&mut self) -> Result<(), String> {
+        match self.send_command(AudioCommand::Start) {
+            AudioStatus::Started => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn stop_recording(&mut self) -> Result<Vec<u8>, String> {
+        match self.send_command(AudioCommand::Stop) {
+            AudioStatus::Stopped(data) => Ok(data),
+            AudioStatus::Error(e) => Err(e),
+            _ => Err("未知的停止录音响应".to_string()),
+        }
+    }
+
+    pub fn pause_recording(&mut self) -> Result<(), String> {
+        match self.send_command(AudioCommand::Pause) {
+            AudioStatus::Paused => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn resume_recording(&mut self) -> Result<(), String> {
+        match self.send_command(AudioCommand::Resume) {
+            AudioStatus::Resumed => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// 开启流式（边说边转写）录音。`runtime` 是发起调用的 tokio 运行时句柄——
+    /// 音频工作线程是普通 `std::thread`，没有自己的运行时，上传任务要靠它来 `spawn`。
+    pub fn start_streaming_recording(&mut self, app_handle: tauri::AppHandle, service_url: String, runtime: tokio::runtime::Handle) -> Result<(), String> {
+        match self.send_command(AudioCommand::StartStreaming { app_handle, service_url, runtime }) {
+            AudioStatus::StreamingStarted => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn stop_streaming_recording(&mut self) -> Result<(), String> {
+        match self.send_command(AudioCommand::StopStreaming) {
+            AudioStatus::StreamingStopped => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// 像 `start_recording` 一样开始录音，并让工作线程在检测到尾部静音达到
+    /// `trailing_silence_ms` 后置位 `auto_stop_triggered`，供前端轮询后调用 `stop_recording`。
+    pub fn start_recording_with_auto_stop(&mut self, trailing_silence_ms: u32) -> Result<(), String> {
+        match self.send_command(AudioCommand::StartAutoStop { trailing_silence_ms }) {
+            AudioStatus::AutoStopArmed => Ok(()),
+            AudioStatus::Error(e) => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn is_auto_stopped(&self) -> bool {
+        self.auto_stop_triggered.load(Ordering::SeqCst)
+    }
+
+    pub fn last_trim_stats(&self) -> Option<TrimStats> {
+        *self.last_trim_stats.lock().unwrap()
+    }
+
+    pub fn set_audio_config(&mut self, config: AudioConfig) {
+        *self.audio_config.lock().unwrap() = config;
+    }
+
+    /// 当前编码器对应的 `wav_format` 标签和 MIME 类型，供上传时填写 multipart 字段
+    pub fn encoder_format(&self) -> (&'static str, &'static str) {
+        let encoder = self.audio_config.lock().unwrap().encoder;
+        (encoder.wav_format_tag(), encoder.mime_type())
+    }
+
+    /// 切换多声道降混方式，立即对下一次回调生效，无需重建音频流
+    pub fn set_channel_mode(&mut self, mode: ChannelMode) {
+        *self.channel_mode.lock().unwrap() = mode;
+    }
+
+    /// 当前已打开设备的声道数，供调用方在切换 `ChannelMode::Channel(n)` 前校验索引范围
+    pub fn channel_count(&self) -> usize {
+        self.channel_count.load(Ordering::SeqCst)
+    }
+
+    /// 注入 AppHandle，用于向前端广播录音电平/波形事件。应在应用 setup 阶段调用一次。
+    pub fn set_app_handle(&mut self, handle: tauri::AppHandle) {
+        *self.app_handle.lock().unwrap() = Some(handle);
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+}
@@ -5,6 +5,7 @@ use tauri::{Manager, State};
 use regex::Regex;
 
 mod audio_manager;
+mod streaming;
 use audio_manager::AudioManager;
 
 struct AppState {
@@ -87,25 +88,27 @@ async fn start_audio_recording(state: State<'_, AppState>) -> Result<String, Str
 
 #[tauri::command]
 async fn stop_audio_recording(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
-    // 先获取音频数据，然后释放锁
-    let pcm_data = {
+    // 先获取音频数据和编码格式，然后释放锁
+    let (encoded_data, wav_format, mime_type) = {
         let mut audio_manager = state.audio_manager.lock().unwrap();
-        audio_manager.stop_recording()?
+        let encoded_data = audio_manager.stop_recording()?;
+        let (wav_format, mime_type) = audio_manager.encoder_format();
+        (encoded_data, wav_format, mime_type)
     };
-    
+
     // 使用本地FunASR服务
     let service_url = std::env::var("LOCAL_SERVICE_URL")
         .unwrap_or_else(|_| "http://localhost:10095".to_string());
-    
+
     println!("使用本地FunASR语音识别服务: {}", service_url);
-    
-    // 发送到本地FunASR服务进行转写（multipart form格式，直接发送PCM数据）
+
+    // 发送到本地FunASR服务进行转写（multipart form格式，按当前编码器选择的格式发送）
     let client = reqwest::Client::new();
     let form = reqwest::multipart::Form::new()
-        .part("file", reqwest::multipart::Part::bytes(pcm_data)
-            .file_name("recording.pcm")
-            .mime_str("audio/pcm").unwrap())
-        .text("wav_format", "pcm")  // 明确指定PCM格式
+        .part("file", reqwest::multipart::Part::bytes(encoded_data)
+            .file_name(format!("recording.{}", wav_format))
+            .mime_str(mime_type).unwrap())
+        .text("wav_format", wav_format)
         .text("itn", "true")
         .text("audio_fs", "16000")
         .text("svs_lang", "auto")
@@ -145,6 +148,111 @@ async fn is_audio_recording(state: State<'_, AppState>) -> Result<bool, String>
     Ok(audio_manager.is_recording())
 }
 
+#[tauri::command]
+async fn pause_audio_recording(state: State<'_, AppState>) -> Result<String, String> {
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.pause_recording()?;
+    Ok("Recording paused".to_string())
+}
+
+#[tauri::command]
+async fn resume_audio_recording(state: State<'_, AppState>) -> Result<String, String> {
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.resume_recording()?;
+    Ok("Recording resumed".to_string())
+}
+
+#[tauri::command]
+async fn list_audio_devices(state: State<'_, AppState>) -> Result<Vec<audio_manager::DeviceInfo>, String> {
+    let audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.list_input_devices()
+}
+
+#[tauri::command]
+async fn set_audio_device(device_name: String, state: State<'_, AppState>) -> Result<String, String> {
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.select_input_device(&device_name)?;
+    Ok(format!("已切换到音频输入设备: {}", device_name))
+}
+
+#[tauri::command]
+async fn start_streaming_recording(app_handle: tauri::AppHandle, state: State<'_, AppState>) -> Result<String, String> {
+    let service_url = std::env::var("LOCAL_SERVICE_URL")
+        .unwrap_or_else(|_| "http://localhost:10095".to_string());
+
+    // 音频工作线程是普通 std::thread，没有自己的 tokio 运行时，
+    // 上传任务要靠这里捕获的句柄来 spawn
+    let runtime = tokio::runtime::Handle::current();
+
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.start_streaming_recording(app_handle, service_url, runtime)?;
+    Ok("Streaming recording started".to_string())
+}
+
+#[tauri::command]
+async fn stop_streaming_recording(state: State<'_, AppState>) -> Result<String, String> {
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.stop_streaming_recording()?;
+    Ok("Streaming recording stopped".to_string())
+}
+
+#[tauri::command]
+async fn start_audio_recording_auto_stop(trailing_silence_ms: u32, state: State<'_, AppState>) -> Result<String, String> {
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.start_recording_with_auto_stop(trailing_silence_ms)?;
+    Ok("Recording started with auto-stop".to_string())
+}
+
+#[tauri::command]
+async fn is_audio_auto_stopped(state: State<'_, AppState>) -> Result<bool, String> {
+    let audio_manager = state.audio_manager.lock().unwrap();
+    Ok(audio_manager.is_auto_stopped())
+}
+
+#[tauri::command]
+async fn get_last_trim_stats(state: State<'_, AppState>) -> Result<Option<audio_manager::TrimStats>, String> {
+    let audio_manager = state.audio_manager.lock().unwrap();
+    Ok(audio_manager.last_trim_stats())
+}
+
+#[tauri::command]
+async fn set_audio_encoder(use_opus: bool, bitrate: Option<i32>, state: State<'_, AppState>) -> Result<String, String> {
+    let encoder = if use_opus {
+        audio_manager::AudioEncoder::Opus { bitrate: bitrate.unwrap_or(24000) }
+    } else {
+        audio_manager::AudioEncoder::Pcm
+    };
+
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+    audio_manager.set_audio_config(audio_manager::AudioConfig { encoder });
+    Ok(format!("音频编码器已切换: {:?}", encoder))
+}
+
+#[tauri::command]
+async fn set_channel_mode(mode: String, channel: Option<usize>, state: State<'_, AppState>) -> Result<String, String> {
+    let mut audio_manager = state.audio_manager.lock().unwrap();
+
+    let channel_mode = match mode.as_str() {
+        "mono0" => audio_manager::ChannelMode::Mono0,
+        "average" => audio_manager::ChannelMode::DownmixAverage,
+        "channel" => {
+            let n = channel.ok_or("选择单一声道模式时缺少 channel 参数")?;
+            let channel_count = audio_manager.channel_count();
+            if n >= channel_count {
+                return Err(format!(
+                    "声道索引 {} 超出当前设备的声道范围 (0..{})",
+                    n, channel_count
+                ));
+            }
+            audio_manager::ChannelMode::Channel(n)
+        }
+        other => return Err(format!("未知的降混模式: {}", other)),
+    };
+
+    audio_manager.set_channel_mode(channel_mode);
+    Ok(format!("声道降混模式已切换: {:?}", channel_mode))
+}
+
 fn main() {
     // 初始化音频管理器
     let mut audio_manager = AudioManager::new().expect("创建音频管理器失败");
@@ -157,14 +265,18 @@ fn main() {
         .plugin(tauri_plugin_store::Builder::default().build())
         .setup(|app| {
             let window = app.get_window("main").unwrap();
-            
+
+            // 注入 AppHandle，供音频管理器广播电平/波形事件
+            let app_state: State<AppState> = app.state();
+            app_state.audio_manager.lock().unwrap().set_app_handle(app.handle());
+
             // 移除窗口阴影
             #[cfg(any(windows, target_os = "macos"))]
             {
                 use window_shadows::set_shadow;
                 set_shadow(&window, false).expect("Unsupported platform!");
             }
-            
+
             #[cfg(debug_assertions)] // 只在调试构建中包含此代码
             {
                 window.open_devtools();
@@ -176,7 +288,18 @@ fn main() {
             stop_python_service,
             start_audio_recording,
             stop_audio_recording,
-            is_audio_recording
+            is_audio_recording,
+            pause_audio_recording,
+            resume_audio_recording,
+            list_audio_devices,
+            set_audio_device,
+            start_streaming_recording,
+            stop_streaming_recording,
+            start_audio_recording_auto_stop,
+            is_audio_auto_stopped,
+            get_last_trim_stats,
+            set_audio_encoder,
+            set_channel_mode
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
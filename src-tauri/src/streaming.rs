@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tauri::Manager;
+use tokio::sync::mpsc;
+
+use crate::audio_manager::{create_pcm, resample};
+
+/// 一次流式（边说边转写）录音会话的后台上传任务句柄。
+/// 发送端由音频工作线程持有，工作线程处理输入样本时按窗口推送，
+/// 这里只负责消费窗口、POST 给 FunASR 的流式识别接口并把结果转发给前端。
+pub struct StreamingSession;
+
+impl StreamingSession {
+    /// 启动后台上传任务。`rx` 接收 `(窗口样本, 是否为最后一个窗口)`。
+    ///
+    /// 音频工作线程是一个普通的 `std::thread`，本身不在 tokio 运行时里，
+    /// 所以上传任务要显式用调用方传入的 `runtime` 句柄 `spawn`，而不能直接 `tokio::spawn`。
+    ///
+    /// `source_sample_rate` 是共享的原子量而不是一次性传入的值：录音过程中
+    /// 热切换输入设备可能改变采样率，工作线程会就地更新它，这样已经在跑的
+    /// 上传任务不用重启也能用正确的源速率重采样后续窗口。
+    pub fn start(
+        app_handle: tauri::AppHandle,
+        service_url: String,
+        source_sample_rate: Arc<AtomicU32>,
+        mut rx: mpsc::UnboundedReceiver<(Vec<f32>, bool)>,
+        runtime: tokio::runtime::Handle,
+    ) -> Self {
+        runtime.spawn(async move {
+            let client = reqwest::Client::new();
+
+            while let Some((window, is_final)) = rx.recv().await {
+                // 非最后一个窗口时，空窗口没有新样本可传，直接跳过；
+                // 但最后一个窗口即使是空的，也必须把 is_final 传给服务端，
+                // 否则前端永远收不到 transcription_final，等待会卡住
+                if window.is_empty() && !is_final {
+                    continue;
+                }
+
+                let current_rate = source_sample_rate.load(Ordering::SeqCst);
+                let resampled = if current_rate != 16000 {
+                    resample(&window, current_rate, 16000)
+                } else {
+                    window
+                };
+
+                let pcm = match create_pcm(resampled) {
+                    Ok(pcm) => pcm,
+                    Err(e) => {
+                        eprintln!("流式转写编码失败: {}", e);
+                        continue;
+                    }
+                };
+
+                let form = reqwest::multipart::Form::new()
+                    .part("file", reqwest::multipart::Part::bytes(pcm)
+                        .file_name("chunk.pcm")
+                        .mime_str("audio/pcm").unwrap())
+                    .text("wav_format", "pcm")
+                    .text("audio_fs", "16000")
+                    .text("is_final", if is_final { "true" } else { "false" });
+
+                let response = match client
+                    .post(format!("{}/transcribe/online", service_url))
+                    .multipart(form)
+                    .send()
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(e) => {
+                        eprintln!("流式转写请求失败: {}", e);
+                        continue;
+                    }
+                };
+
+                let result: serde_json::Value = match response.json().await {
+                    Ok(result) => result,
+                    Err(e) => {
+                        eprintln!("解析流式转写结果失败: {}", e);
+                        continue;
+                    }
+                };
+
+                let event = if is_final { "transcription_final" } else { "transcription_partial" };
+                let _ = app_handle.emit_all(event, result);
+
+                if is_final {
+                    break;
+                }
+            }
+        });
+
+        Self
+    }
+}